@@ -0,0 +1,52 @@
+use super::*;
+
+/// Describes a single input parameter to be created by
+/// [`add_node_from_descriptor`](Graph::add_node_from_descriptor).
+pub struct InputDescriptor<DataType, ValueType> {
+    pub name: String,
+    pub data_type: DataType,
+    pub value: ValueType,
+    pub kind: InputParamKind,
+    pub shown_inline: bool,
+}
+
+/// Describes a node's full interface (inputs and outputs) so it can be built in one
+/// call via [`Graph::add_node_from_descriptor`], instead of a hand-written `add_node`
+/// closure. Useful for generating nodes programmatically from external schemas, such
+/// as device definitions or plugin manifests.
+pub struct NodeDescriptor<DataType, ValueType> {
+    pub label: String,
+    pub inputs: Vec<InputDescriptor<DataType, ValueType>>,
+    pub outputs: Vec<(String, DataType)>,
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Instantiates a node from a [`NodeDescriptor`] in one call, adding all of its
+    /// input and output params.
+    pub fn add_node_from_descriptor(
+        &mut self,
+        desc: NodeDescriptor<DataType, ValueType>,
+        user_data: NodeData,
+    ) -> NodeId {
+        let NodeDescriptor {
+            label,
+            inputs,
+            outputs,
+        } = desc;
+        self.add_node(label, user_data, move |graph, node_id| {
+            for input in inputs {
+                graph.add_input_param(
+                    node_id,
+                    input.name,
+                    input.data_type,
+                    input.value,
+                    input.kind,
+                    input.shown_inline,
+                );
+            }
+            for (name, typ) in outputs {
+                graph.add_output_param(node_id, name, typ);
+            }
+        })
+    }
+}
@@ -0,0 +1,204 @@
+use super::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// An incremental evaluation layer over [`Graph`]. Tracks which nodes are dirty since
+/// the last [`evaluate`](GraphEvaluator::evaluate) call and caches the output values
+/// produced for clean nodes, so edits only recompute the downstream cone they affect
+/// instead of the whole graph.
+///
+/// `GraphEvaluator` has no way to observe mutations made directly through `Graph`'s
+/// own methods (`add_connection`, `remove_connection`, `change_node_input_type`,
+/// `set_input_value`, ...) — it only knows about dirty nodes because its own
+/// `connect`/`disconnect`/`change_input_type`/`set_input_value` wrappers mark them.
+/// Once a `Graph` is paired with a `GraphEvaluator`, route every mutation that should
+/// affect evaluation through the evaluator's wrappers rather than calling the
+/// equivalent `Graph` method directly, or `evaluate` will return stale cached values
+/// for the edit's downstream cone.
+pub struct GraphEvaluator<ValueType> {
+    dirty: HashSet<NodeId>,
+    cache: HashMap<OutputId, ValueType>,
+}
+
+impl<ValueType> GraphEvaluator<ValueType> {
+    pub fn new() -> Self {
+        Self {
+            dirty: HashSet::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Marks `node_id` and everything downstream of it (following `connections` from
+    /// output-node to input-node) as dirty.
+    pub fn mark_dirty<NodeData, DataType>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+    ) {
+        let mut queue = VecDeque::new();
+        queue.push_back(node_id);
+        while let Some(current) = queue.pop_front() {
+            if !self.dirty.insert(current) {
+                continue;
+            }
+            for (input, output) in graph.iter_connections() {
+                if graph.get_output(output).node == current {
+                    queue.push_back(graph.get_input(input).node());
+                }
+            }
+        }
+    }
+
+    /// Connects `output` to `input` and marks the input's node (and its downstream
+    /// cone) dirty.
+    pub fn connect<NodeData, DataType>(
+        &mut self,
+        graph: &mut Graph<NodeData, DataType, ValueType>,
+        output: OutputId,
+        input: InputId,
+    ) {
+        graph.add_connection(output, input);
+        self.mark_dirty(graph, graph.get_input(input).node());
+    }
+
+    /// Disconnects `input` and marks its node (and its downstream cone) dirty.
+    pub fn disconnect<NodeData, DataType>(
+        &mut self,
+        graph: &mut Graph<NodeData, DataType, ValueType>,
+        input: InputId,
+    ) {
+        let node_id = graph.get_input(input).node();
+        graph.remove_connection(input);
+        self.mark_dirty(graph, node_id);
+    }
+
+    /// Changes an input's data/value type and marks its node (and its downstream
+    /// cone) dirty.
+    pub fn change_input_type<NodeData, DataType>(
+        &mut self,
+        graph: &mut Graph<NodeData, DataType, ValueType>,
+        node_id: NodeId,
+        input_name: &str,
+        new_data_type: DataType,
+        new_value_type: ValueType,
+    ) -> Result<(), EguiGraphError> {
+        graph.change_node_input_type(node_id, input_name, new_data_type, new_value_type)?;
+        self.mark_dirty(graph, node_id);
+        Ok(())
+    }
+
+    /// Overwrites an input's value and marks its node (and its downstream cone)
+    /// dirty.
+    pub fn set_input_value<NodeData, DataType>(
+        &mut self,
+        graph: &mut Graph<NodeData, DataType, ValueType>,
+        input: InputId,
+        value: ValueType,
+    ) {
+        let node_id = graph.get_input(input).node();
+        graph.set_input_value(input, value);
+        self.mark_dirty(graph, node_id);
+    }
+
+    /// Visits dirty nodes in topological order, calling `f` with each node's inputs
+    /// (paired with the output currently feeding them, if any) and caching the output
+    /// values it returns. Clears the dirty set once done.
+    pub fn evaluate<NodeData, DataType, F>(
+        &mut self,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        mut f: F,
+    ) -> Result<(), EguiGraphError>
+    where
+        F: FnMut(NodeId, &[(InputId, Option<OutputId>)]) -> Vec<(OutputId, ValueType)>,
+    {
+        let order = graph.topological_order()?;
+        for node_id in order {
+            if !self.dirty.contains(&node_id) {
+                continue;
+            }
+            let inputs: Vec<(InputId, Option<OutputId>)> = graph[node_id]
+                .input_ids()
+                .map(|input_id| (input_id, graph.connection(input_id)))
+                .collect();
+            for (output_id, value) in f(node_id, &inputs) {
+                self.cache.insert(output_id, value);
+            }
+        }
+        self.dirty.clear();
+        Ok(())
+    }
+
+    /// Returns the cached value for `output` as of the last `evaluate` call.
+    pub fn cached_value(&self, output: OutputId) -> Option<&ValueType> {
+        self.cache.get(&output)
+    }
+}
+
+impl<ValueType> Default for GraphEvaluator<ValueType> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, PartialEq)]
+    enum TestType {
+        Number,
+    }
+
+    fn node_with_io(graph: &mut Graph<(), TestType, f64>, label: &str) -> NodeId {
+        graph.add_node(label.to_string(), (), |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "in".into(),
+                TestType::Number,
+                0.0,
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+            graph.add_output_param(node_id, "out".into(), TestType::Number);
+        })
+    }
+
+    #[test]
+    fn connecting_through_the_evaluator_marks_the_downstream_cone_dirty() {
+        let mut graph = Graph::<(), TestType, f64>::new();
+        let source = node_with_io(&mut graph, "source");
+        let sink = node_with_io(&mut graph, "sink");
+        let source_output = graph[source].get_output("out").unwrap();
+        let sink_input = graph[sink].get_input("in").unwrap();
+
+        let mut evaluator = GraphEvaluator::<f64>::new();
+        evaluator.connect(&mut graph, source_output, sink_input);
+
+        let mut visited = Vec::new();
+        evaluator
+            .evaluate(&graph, |node_id, _inputs| {
+                visited.push(node_id);
+                Vec::new()
+            })
+            .unwrap();
+        assert!(visited.contains(&sink));
+    }
+
+    #[test]
+    fn evaluate_clears_dirty_set_so_a_second_call_visits_nothing() {
+        let mut graph = Graph::<(), TestType, f64>::new();
+        let node_id = node_with_io(&mut graph, "solo");
+
+        let mut evaluator = GraphEvaluator::<f64>::new();
+        evaluator.mark_dirty(&graph, node_id);
+        evaluator.evaluate(&graph, |_, _| Vec::new()).unwrap();
+
+        let mut visited = false;
+        evaluator
+            .evaluate(&graph, |_, _| {
+                visited = true;
+                Vec::new()
+            })
+            .unwrap();
+        assert!(!visited);
+    }
+}
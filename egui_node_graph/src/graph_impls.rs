@@ -1,4 +1,5 @@
 use super::*;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
     pub fn new() -> Self {
@@ -7,6 +8,10 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
             inputs: SlotMap::default(),
             outputs: SlotMap::default(),
             connections: SecondaryMap::default(),
+            label2node: HashMap::default(),
+            graph_inputs: Vec::default(),
+            graph_outputs: Vec::default(),
+            properties: HashMap::default(),
         }
     }
 
@@ -19,13 +24,14 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
         let node_id = self.nodes.insert_with_key(|node_id| {
             Node {
                 id: node_id,
-                label,
+                label: label.clone(),
                 // These get filled in later by the user function
                 inputs: Vec::default(),
                 outputs: Vec::default(),
                 user_data,
             }
         });
+        self.label2node.insert(label, node_id);
 
         f(self, node_id);
 
@@ -67,16 +73,73 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
         self.connections
             .retain(|i, o| !(self.outputs[*o].node == node_id || self.inputs[i].node == node_id));
         let inputs: SVec<_> = self[node_id].input_ids().collect();
-        for input in inputs {
+        for &input in &inputs {
             self.inputs.remove(input);
         }
+        self.graph_inputs.retain(|id| !inputs.contains(id));
         let outputs: SVec<_> = self[node_id].output_ids().collect();
-        for output in outputs {
+        for &output in &outputs {
             self.outputs.remove(output);
         }
+        self.graph_outputs.retain(|id| !outputs.contains(id));
+        let label = self[node_id].label.clone();
+        if self.label2node.get(&label) == Some(&node_id) {
+            self.label2node.remove(&label);
+            // Another node shared this label: keep the index pointing at a survivor
+            // instead of dropping the entry outright.
+            if let Some(survivor) = self
+                .iter_nodes()
+                .find(|&id| id != node_id && self[id].label == label)
+            {
+                self.label2node.insert(label, survivor);
+            }
+        }
         self.nodes.remove(node_id);
     }
 
+    /// O(1) lookup for a node by its display label, kept in sync by `add_node` and
+    /// `remove_node`. If labels aren't unique, later nodes with the same label shadow
+    /// earlier ones in the index.
+    pub fn node_by_label(&self, label: &str) -> Option<NodeId> {
+        self.label2node.get(label).copied()
+    }
+
+    /// Marks `input` as part of this graph's external interface, so the graph can be
+    /// treated as a reusable composite node by a parent graph.
+    pub fn mark_graph_input(&mut self, input: InputId) {
+        if !self.graph_inputs.contains(&input) {
+            self.graph_inputs.push(input);
+        }
+    }
+
+    /// Marks `output` as part of this graph's external interface.
+    pub fn mark_graph_output(&mut self, output: OutputId) {
+        if !self.graph_outputs.contains(&output) {
+            self.graph_outputs.push(output);
+        }
+    }
+
+    pub fn graph_inputs(&self) -> &[InputId] {
+        &self.graph_inputs
+    }
+
+    pub fn graph_outputs(&self) -> &[OutputId] {
+        &self.graph_outputs
+    }
+
+    /// Sets a graph-level metadata property, e.g. for a name or description that
+    /// doesn't belong to any single node.
+    pub fn set_property(&mut self, name: impl Into<String>, value: ValueType) {
+        self.properties.insert(name.into(), value);
+    }
+
+    pub fn property(&self, name: &str) -> Option<&ValueType> {
+        self.properties.get(name)
+    }
+
+    /// Does not mark anything dirty for incremental evaluation; if this graph is
+    /// paired with a [`GraphEvaluator`](crate::GraphEvaluator), call
+    /// [`GraphEvaluator::disconnect`](crate::GraphEvaluator::disconnect) instead.
     pub fn remove_connection(&mut self, input_id: InputId) -> Option<OutputId> {
         self.connections.remove(input_id)
     }
@@ -85,10 +148,98 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
         self.nodes.iter().map(|(id, _)| id)
     }
 
+    /// Does not mark anything dirty for incremental evaluation; if this graph is
+    /// paired with a [`GraphEvaluator`](crate::GraphEvaluator), call
+    /// [`GraphEvaluator::connect`](crate::GraphEvaluator::connect) instead.
     pub fn add_connection(&mut self, output: OutputId, input: InputId) {
         self.connections.insert(input, output);
     }
 
+    /// Like [`add_connection`](Self::add_connection), but refuses the edge if it would
+    /// close a cycle, instead of silently creating one.
+    pub fn try_add_connection(
+        &mut self,
+        output: OutputId,
+        input: InputId,
+    ) -> Result<(), EguiGraphError> {
+        let output_node = self.outputs[output].node;
+        let input_node = self.inputs[input].node;
+        if self.can_reach(input_node, output_node) {
+            return Err(EguiGraphError::CycleDetected(vec![input_node, output_node]));
+        }
+        self.add_connection(output, input);
+        Ok(())
+    }
+
+    /// DFS reachability test: can `from` reach `to` by following existing connections
+    /// forward (output-node to input-node)?
+    fn can_reach(&self, from: NodeId, to: NodeId) -> bool {
+        if from == to {
+            return true;
+        }
+        let mut visited = HashSet::new();
+        let mut stack = vec![from];
+        while let Some(node_id) = stack.pop() {
+            if !visited.insert(node_id) {
+                continue;
+            }
+            for (input, output) in self.iter_connections() {
+                if self.outputs[output].node == node_id {
+                    let next = self.inputs[input].node;
+                    if next == to {
+                        return true;
+                    }
+                    stack.push(next);
+                }
+            }
+        }
+        false
+    }
+
+    /// Computes an evaluation order for the graph using Kahn's algorithm, or, if the
+    /// graph isn't a DAG, reports the nodes left with a positive in-degree: those on a
+    /// cycle and those downstream of one.
+    pub fn topological_order(&self) -> Result<Vec<NodeId>, EguiGraphError> {
+        let mut in_degree: SecondaryMap<NodeId, usize> = SecondaryMap::default();
+        let mut downstream: SecondaryMap<NodeId, Vec<NodeId>> = SecondaryMap::default();
+        for node_id in self.iter_nodes() {
+            in_degree.insert(node_id, 0);
+            downstream.insert(node_id, Vec::new());
+        }
+        for (input, output) in self.iter_connections() {
+            let from = self.outputs[output].node;
+            let to = self.inputs[input].node;
+            downstream[from].push(to);
+            in_degree[to] += 1;
+        }
+
+        let mut queue: VecDeque<NodeId> = self
+            .iter_nodes()
+            .filter(|node_id| in_degree[*node_id] == 0)
+            .collect();
+
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(node_id) = queue.pop_front() {
+            order.push(node_id);
+            for &next in &downstream[node_id] {
+                in_degree[next] -= 1;
+                if in_degree[next] == 0 {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        if order.len() < self.nodes.len() {
+            let remaining = self
+                .iter_nodes()
+                .filter(|node_id| in_degree[*node_id] > 0)
+                .collect();
+            return Err(EguiGraphError::CycleDetected(remaining));
+        }
+
+        Ok(order)
+    }
+
     pub fn iter_connections(&self) -> impl Iterator<Item = (InputId, OutputId)> + '_ {
         self.connections.iter().map(|(o, i)| (o, *i))
     }
@@ -113,6 +264,10 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
         &self.outputs[output]
     }
 
+    /// Does not mark anything dirty for incremental evaluation; if this graph is
+    /// paired with a [`GraphEvaluator`](crate::GraphEvaluator), call
+    /// [`GraphEvaluator::change_input_type`](crate::GraphEvaluator::change_input_type)
+    /// instead.
     pub fn change_node_input_type(&mut self, node_id: NodeId, input_name: &str, new_data_type: DataType, new_value_type: ValueType) -> Result<(), EguiGraphError>{
         let node = self.nodes.get(node_id).ok_or(EguiGraphError::UnknownNode(node_id))?;
         let input_id = node.get_input(input_name)?;
@@ -134,12 +289,23 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
         }
     }
 
-    fn change_output_type(&mut self, output_id: OutputId, new_data_type: DataType) {        
+    fn change_output_type(&mut self, output_id: OutputId, new_data_type: DataType) {
         if let Some(output) = self.outputs.get_mut(output_id) {
             output.typ = new_data_type;
             self.connections.retain(|_, o| *o != output_id);
         }
     }
+
+    /// Overwrites the value held by an input param, leaving its type and connection
+    /// untouched. Does not mark anything dirty for incremental evaluation; if this
+    /// graph is paired with a [`GraphEvaluator`](crate::GraphEvaluator), call
+    /// [`GraphEvaluator::set_input_value`](crate::GraphEvaluator::set_input_value)
+    /// instead.
+    pub fn set_input_value(&mut self, input_id: InputId, value: ValueType) {
+        if let Some(input) = self.inputs.get_mut(input_id) {
+            input.value = value;
+        }
+    }
 }
 
 impl<NodeData, DataType, ValueType> Default for Graph<NodeData, DataType, ValueType> {
@@ -206,3 +372,55 @@ impl<DataType, ValueType> InputParam<DataType, ValueType> {
         self.value = new_value_type;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(graph: &mut Graph<(), (), ()>, label: &str) -> NodeId {
+        graph.add_node(label.to_string(), (), |_, _| {})
+    }
+
+    #[test]
+    fn topological_order_respects_connections() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let a = node(&mut graph, "a");
+        let b = node(&mut graph, "b");
+        let c = node(&mut graph, "c");
+        let a_out = graph.add_output_param(a, "out".into(), ());
+        let b_in = graph.add_input_param(b, "in".into(), (), (), InputParamKind::ConnectionOnly, true);
+        let b_out = graph.add_output_param(b, "out".into(), ());
+        let c_in = graph.add_input_param(c, "in".into(), (), (), InputParamKind::ConnectionOnly, true);
+        graph.add_connection(a_out, b_in);
+        graph.add_connection(b_out, c_in);
+
+        let order = graph.topological_order().unwrap();
+        let pos = |id: NodeId| order.iter().position(|&n| n == id).unwrap();
+        assert!(pos(a) < pos(b));
+        assert!(pos(b) < pos(c));
+    }
+
+    #[test]
+    fn try_add_connection_refuses_a_cycle() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let a = node(&mut graph, "a");
+        let b = node(&mut graph, "b");
+        let a_in = graph.add_input_param(a, "in".into(), (), (), InputParamKind::ConnectionOnly, true);
+        let a_out = graph.add_output_param(a, "out".into(), ());
+        let b_in = graph.add_input_param(b, "in".into(), (), (), InputParamKind::ConnectionOnly, true);
+        let b_out = graph.add_output_param(b, "out".into(), ());
+        graph.add_connection(a_out, b_in);
+
+        assert!(graph.try_add_connection(b_out, a_in).is_err());
+        assert!(graph.connection(a_in).is_none());
+    }
+
+    #[test]
+    fn remove_node_restores_label2node_to_a_surviving_duplicate() {
+        let mut graph = Graph::<(), (), ()>::new();
+        let first = node(&mut graph, "dup");
+        let second = node(&mut graph, "dup");
+        graph.remove_node(first);
+        assert_eq!(graph.node_by_label("dup"), Some(second));
+    }
+}
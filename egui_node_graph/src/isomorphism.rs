@@ -0,0 +1,303 @@
+use super::*;
+use std::cell::RefCell;
+use std::collections::{hash_map::DefaultHasher, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// Assigns small, stable integer classes to `DataType` values using a caller-supplied
+/// equality function, since `DataType` isn't required to implement `Eq`/`Hash`. Shared
+/// between both graphs being compared so that equal types get the same class.
+struct TypeClasses<'a, DataType> {
+    eq: &'a dyn Fn(&DataType, &DataType) -> bool,
+    classes: RefCell<Vec<DataType>>,
+}
+
+impl<'a, DataType: Clone> TypeClasses<'a, DataType> {
+    fn new(eq: &'a dyn Fn(&DataType, &DataType) -> bool) -> Self {
+        Self {
+            eq,
+            classes: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn class_of(&self, typ: &DataType) -> u64 {
+        let mut classes = self.classes.borrow_mut();
+        if let Some(idx) = classes.iter().position(|existing| (self.eq)(existing, typ)) {
+            return idx as u64;
+        }
+        classes.push(typ.clone());
+        (classes.len() - 1) as u64
+    }
+}
+
+fn color_histogram(colors: &HashMap<NodeId, u64>) -> Vec<(u64, usize)> {
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for &color in colors.values() {
+        *counts.entry(color).or_insert(0) += 1;
+    }
+    let mut hist: Vec<_> = counts.into_iter().collect();
+    hist.sort();
+    hist
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Structural equality up to `SlotMap` key relabeling: two graphs built in a
+    /// different insertion order (and so holding different keys) compare equal here
+    /// if one can be obtained from the other by renaming node/param ids alone.
+    /// Only node labels, param names, param data types (via `data_type_eq`) and
+    /// connections are compared; input values, input kinds, `shown_inline` and node
+    /// user data are not.
+    ///
+    /// Uses iterative color refinement to partition nodes by structural role, then
+    /// falls back to backtracking search among nodes refinement couldn't tell apart.
+    pub fn is_isomorphic_to(
+        &self,
+        other: &Self,
+        data_type_eq: impl Fn(&DataType, &DataType) -> bool,
+    ) -> bool
+    where
+        DataType: Clone,
+    {
+        if self.nodes.len() != other.nodes.len()
+            || self.connections.len() != other.connections.len()
+        {
+            return false;
+        }
+
+        let type_classes = TypeClasses::new(&data_type_eq);
+        let mut self_colors = self.initial_colors(&type_classes);
+        let mut other_colors = other.initial_colors(&type_classes);
+
+        for _ in 0..=self.nodes.len() {
+            if color_histogram(&self_colors) != color_histogram(&other_colors) {
+                return false;
+            }
+            let next_self = self.refine_colors(&self_colors);
+            let next_other = other.refine_colors(&other_colors);
+            let stabilized = color_histogram(&next_self).len() == color_histogram(&self_colors).len()
+                && color_histogram(&next_other).len() == color_histogram(&other_colors).len();
+            self_colors = next_self;
+            other_colors = next_other;
+            if stabilized {
+                break;
+            }
+        }
+
+        if color_histogram(&self_colors) != color_histogram(&other_colors) {
+            return false;
+        }
+
+        let mut mapping = HashMap::new();
+        self.backtrack_match(other, &self_colors, &other_colors, &mut mapping)
+    }
+
+    fn initial_colors(&self, type_classes: &TypeClasses<DataType>) -> HashMap<NodeId, u64>
+    where
+        DataType: Clone,
+    {
+        self.iter_nodes()
+            .map(|id| {
+                let node = &self[id];
+                let mut inputs: Vec<(String, u64)> = node
+                    .inputs
+                    .iter()
+                    .map(|(name, input_id)| {
+                        (
+                            name.clone(),
+                            type_classes.class_of(&self.get_input(*input_id).typ),
+                        )
+                    })
+                    .collect();
+                inputs.sort();
+                let mut outputs: Vec<(String, u64)> = node
+                    .outputs
+                    .iter()
+                    .map(|(name, output_id)| {
+                        (
+                            name.clone(),
+                            type_classes.class_of(&self.get_output(*output_id).typ),
+                        )
+                    })
+                    .collect();
+                outputs.sort();
+
+                let mut hasher = DefaultHasher::new();
+                node.label.hash(&mut hasher);
+                inputs.hash(&mut hasher);
+                outputs.hash(&mut hasher);
+                (id, hasher.finish())
+            })
+            .collect()
+    }
+
+    fn refine_colors(&self, colors: &HashMap<NodeId, u64>) -> HashMap<NodeId, u64> {
+        self.iter_nodes()
+            .map(|id| {
+                let mut incoming = Vec::new();
+                let mut outgoing = Vec::new();
+                for (input, output) in self.iter_connections() {
+                    let input_node = self.inputs[input].node;
+                    let output_node = self.outputs[output].node;
+                    if input_node == id {
+                        incoming.push(colors[&output_node]);
+                    }
+                    if output_node == id {
+                        outgoing.push(colors[&input_node]);
+                    }
+                }
+                incoming.sort();
+                outgoing.sort();
+
+                let mut hasher = DefaultHasher::new();
+                colors[&id].hash(&mut hasher);
+                incoming.hash(&mut hasher);
+                outgoing.hash(&mut hasher);
+                (id, hasher.finish())
+            })
+            .collect()
+    }
+
+    fn backtrack_match(
+        &self,
+        other: &Self,
+        self_colors: &HashMap<NodeId, u64>,
+        other_colors: &HashMap<NodeId, u64>,
+        mapping: &mut HashMap<NodeId, NodeId>,
+    ) -> bool {
+        let next = self.iter_nodes().find(|id| !mapping.contains_key(id));
+        let self_node = match next {
+            Some(id) => id,
+            None => return self.connections_consistent(other, mapping),
+        };
+
+        let candidates: Vec<NodeId> = other
+            .iter_nodes()
+            .filter(|other_node| {
+                self_colors[&self_node] == other_colors[other_node]
+                    && !mapping.values().any(|mapped| mapped == other_node)
+            })
+            .collect();
+
+        for candidate in candidates {
+            mapping.insert(self_node, candidate);
+            if self.backtrack_match(other, self_colors, other_colors, mapping) {
+                return true;
+            }
+            mapping.remove(&self_node);
+        }
+        false
+    }
+
+    fn connections_consistent(&self, other: &Self, mapping: &HashMap<NodeId, NodeId>) -> bool {
+        for (input, output) in self.iter_connections() {
+            let self_input_node = self.inputs[input].node;
+            let self_output_node = self.outputs[output].node;
+            let (other_input_node, other_output_node) =
+                match (mapping.get(&self_input_node), mapping.get(&self_output_node)) {
+                    (Some(&i), Some(&o)) => (i, o),
+                    _ => return false,
+                };
+
+            let input_name = &self[self_input_node]
+                .inputs
+                .iter()
+                .find(|(_, id)| *id == input)
+                .unwrap()
+                .0;
+            let output_name = &self[self_output_node]
+                .outputs
+                .iter()
+                .find(|(_, id)| *id == output)
+                .unwrap()
+                .0;
+
+            let matches = other[other_input_node]
+                .get_input(input_name)
+                .ok()
+                .and_then(|other_input_id| other.connection(other_input_id))
+                .map_or(false, |other_output_id| {
+                    other[other_output_node].get_output(output_name).ok() == Some(other_output_id)
+                });
+            if !matches {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, PartialEq)]
+    enum TestType {
+        Number,
+    }
+
+    fn number_eq(a: &TestType, b: &TestType) -> bool {
+        matches!((a, b), (TestType::Number, TestType::Number))
+    }
+
+    fn chain(labels: &[&str]) -> Graph<(), TestType, ()> {
+        let mut graph = Graph::<(), TestType, ()>::new();
+        let mut prev_output = None;
+        for &label in labels {
+            let node_id = graph.add_node(label.to_string(), (), |graph, node_id| {
+                graph.add_input_param(
+                    node_id,
+                    "in".into(),
+                    TestType::Number,
+                    (),
+                    InputParamKind::ConnectionOnly,
+                    true,
+                );
+                graph.add_output_param(node_id, "out".into(), TestType::Number);
+            });
+            let input_id = graph[node_id].get_input("in").unwrap();
+            if let Some(output_id) = prev_output {
+                graph.add_connection(output_id, input_id);
+            }
+            prev_output = Some(graph[node_id].get_output("out").unwrap());
+        }
+        graph
+    }
+
+    #[test]
+    fn identical_shape_built_in_different_order_is_isomorphic() {
+        let forward = chain(&["a", "b", "c"]);
+        // The same a -> b -> c chain, with its nodes inserted in reverse order so they
+        // get different `SlotMap` keys.
+        let mut reversed = Graph::<(), TestType, ()>::new();
+        let mut ids = HashMap::new();
+        for &label in ["c", "b", "a"].iter() {
+            let node_id = reversed.add_node(label.to_string(), (), |graph, node_id| {
+                graph.add_input_param(
+                    node_id,
+                    "in".into(),
+                    TestType::Number,
+                    (),
+                    InputParamKind::ConnectionOnly,
+                    true,
+                );
+                graph.add_output_param(node_id, "out".into(), TestType::Number);
+            });
+            ids.insert(label, node_id);
+        }
+        for &(from, to) in [("a", "b"), ("b", "c")].iter() {
+            let output_id = reversed[ids[from]].get_output("out").unwrap();
+            let input_id = reversed[ids[to]].get_input("in").unwrap();
+            reversed.add_connection(output_id, input_id);
+        }
+        assert!(forward.is_isomorphic_to(&reversed, number_eq));
+    }
+
+    #[test]
+    fn different_connection_shape_is_not_isomorphic() {
+        let a_chain = chain(&["a", "b", "c"]);
+        let mut disconnected = chain(&["a", "b", "c"]);
+        let b_id = disconnected.node_by_label("b").unwrap();
+        let b_input = disconnected[b_id].get_input("in").unwrap();
+        disconnected.remove_connection(b_input);
+        assert!(!a_chain.is_isomorphic_to(&disconnected, number_eq));
+    }
+}
@@ -0,0 +1,691 @@
+use super::*;
+
+/// Identifies a node by its label rather than its `SlotMap` key, so a patch built
+/// against one graph instance can be resolved against another where keys aren't
+/// stable (e.g. after loading a save file, or on a collaborator's machine).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodePath(pub String);
+
+/// Identifies a parameter by the label of its owning node plus its own name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ParamPath {
+    pub node: NodePath,
+    pub name: String,
+}
+
+/// A single reversible change to a `Graph`, addressed by content path instead of
+/// `SlotMap` key. Note that, unlike node removal, individual param removal isn't
+/// modeled here: params are only ever added or changed, and go away along with the
+/// node that owns them.
+#[derive(Clone, Debug)]
+pub enum GraphOp<DataType, ValueType> {
+    AddNode {
+        label: String,
+    },
+    /// Carries a snapshot of the removed node's inputs, outputs, and incoming
+    /// connections, so that inverting this op restores the node with its params and
+    /// incoming connections rather than leaving an empty shell behind. The node's
+    /// user data and its outgoing connections are not part of the snapshot.
+    RemoveNode {
+        label: String,
+        inputs: Vec<(String, DataType, ValueType, InputParamKind, bool)>,
+        outputs: Vec<(String, DataType)>,
+        connections: Vec<(String, ParamPath)>,
+    },
+    AddInput {
+        node: NodePath,
+        name: String,
+        typ: DataType,
+        value: ValueType,
+        kind: InputParamKind,
+        shown_inline: bool,
+    },
+    AddOutput {
+        node: NodePath,
+        name: String,
+        typ: DataType,
+    },
+    Connect {
+        output: ParamPath,
+        input: ParamPath,
+    },
+    Disconnect {
+        output: ParamPath,
+        input: ParamPath,
+    },
+    /// Only emitted when an input's *type* differs; a value-only edit on an
+    /// otherwise-unchanged input is a [`GraphOp::SetInputValue`] instead. Carries the
+    /// input's connection on both sides of the change, since `change_node_input_type`
+    /// always drops it — without these, replaying this op on a still-connected input
+    /// would silently sever it even when the connection itself never changed.
+    ChangeInputType {
+        input: ParamPath,
+        old_type: DataType,
+        old_value: ValueType,
+        new_type: DataType,
+        new_value: ValueType,
+        old_connection: Option<ParamPath>,
+        new_connection: Option<ParamPath>,
+    },
+    /// Overwrites an input's value without touching its type or connection.
+    SetInputValue {
+        input: ParamPath,
+        old_value: ValueType,
+        new_value: ValueType,
+    },
+    /// Carries every input this output fed on both sides of the change, for the same
+    /// reason [`GraphOp::ChangeInputType`] carries its connection: `change_node_output_type`
+    /// drops all of an output's downstream connections, and the op needs to be
+    /// self-sufficient enough to restore them even when applied or inverted in
+    /// isolation from the rest of the patch.
+    ChangeOutputType {
+        output: ParamPath,
+        old_type: DataType,
+        new_type: DataType,
+        old_connections: Vec<ParamPath>,
+        new_connections: Vec<ParamPath>,
+    },
+}
+
+/// An ordered list of [`GraphOp`]s turning one graph state into another. Built with
+/// [`Graph::diff`], applied with [`Graph::apply`], and reversed with
+/// [`GraphPatch::invert`] to support undo/redo and simple merging.
+#[derive(Clone, Debug, Default)]
+pub struct GraphPatch<DataType, ValueType> {
+    pub ops: Vec<GraphOp<DataType, ValueType>>,
+}
+
+/// Orders ops the way `apply` needs to see them: nodes are created before their
+/// params, params before rewiring, and disconnects before connects (applying a
+/// `Disconnect` drops whatever feeds its input, so when an input is rewired from one
+/// output to another the old edge is removed before the new one is made, not after).
+/// `diff` emits ops in this order already; `invert` re-sorts by it since
+/// reversing op order on its own doesn't preserve it.
+fn op_phase<DataType, ValueType>(op: &GraphOp<DataType, ValueType>) -> u8 {
+    match op {
+        GraphOp::AddNode { .. } => 0,
+        GraphOp::RemoveNode { .. } => 1,
+        GraphOp::AddInput { .. }
+        | GraphOp::AddOutput { .. }
+        | GraphOp::ChangeInputType { .. }
+        | GraphOp::SetInputValue { .. }
+        | GraphOp::ChangeOutputType { .. } => 2,
+        GraphOp::Disconnect { .. } => 3,
+        GraphOp::Connect { .. } => 4,
+    }
+}
+
+impl<DataType: Clone, ValueType: Clone> GraphPatch<DataType, ValueType> {
+    /// Builds the patch that undoes this one: each op is swapped for its inverse,
+    /// and the result is re-ordered by [`op_phase`] so replay stays valid. Inverting
+    /// `RemoveNode` expands into recreating the node plus all of its captured inputs,
+    /// outputs, and incoming connections. `AddInput`/`AddOutput` have no narrower
+    /// inverse (see the note on [`GraphOp`]) and invert to themselves.
+    pub fn invert(&self) -> Self {
+        let mut ops: Vec<GraphOp<DataType, ValueType>> = self
+            .ops
+            .iter()
+            .rev()
+            .cloned()
+            .flat_map(|op| -> Vec<GraphOp<DataType, ValueType>> {
+                match op {
+                    GraphOp::AddNode { label } => vec![GraphOp::RemoveNode {
+                        label,
+                        inputs: Vec::new(),
+                        outputs: Vec::new(),
+                        connections: Vec::new(),
+                    }],
+                    GraphOp::RemoveNode {
+                        label,
+                        inputs,
+                        outputs,
+                        connections,
+                    } => {
+                        let node = NodePath(label.clone());
+                        let mut expanded = vec![GraphOp::AddNode { label }];
+                        expanded.extend(inputs.into_iter().map(
+                            |(name, typ, value, kind, shown_inline)| GraphOp::AddInput {
+                                node: node.clone(),
+                                name,
+                                typ,
+                                value,
+                                kind,
+                                shown_inline,
+                            },
+                        ));
+                        expanded.extend(outputs.into_iter().map(|(name, typ)| {
+                            GraphOp::AddOutput {
+                                node: node.clone(),
+                                name,
+                                typ,
+                            }
+                        }));
+                        expanded.extend(connections.into_iter().map(|(input_name, output)| {
+                            GraphOp::Connect {
+                                output,
+                                input: ParamPath {
+                                    node: node.clone(),
+                                    name: input_name,
+                                },
+                            }
+                        }));
+                        expanded
+                    }
+                    GraphOp::Connect { output, input } => {
+                        vec![GraphOp::Disconnect { output, input }]
+                    }
+                    GraphOp::Disconnect { output, input } => {
+                        vec![GraphOp::Connect { output, input }]
+                    }
+                    GraphOp::ChangeInputType {
+                        input,
+                        old_type,
+                        old_value,
+                        new_type,
+                        new_value,
+                        old_connection,
+                        new_connection,
+                    } => vec![GraphOp::ChangeInputType {
+                        input,
+                        old_type: new_type,
+                        old_value: new_value,
+                        new_type: old_type,
+                        new_value: old_value,
+                        old_connection: new_connection,
+                        new_connection: old_connection,
+                    }],
+                    GraphOp::SetInputValue {
+                        input,
+                        old_value,
+                        new_value,
+                    } => vec![GraphOp::SetInputValue {
+                        input,
+                        old_value: new_value,
+                        new_value: old_value,
+                    }],
+                    GraphOp::ChangeOutputType {
+                        output,
+                        old_type,
+                        new_type,
+                        old_connections,
+                        new_connections,
+                    } => vec![GraphOp::ChangeOutputType {
+                        output,
+                        old_type: new_type,
+                        new_type: old_type,
+                        old_connections: new_connections,
+                        new_connections: old_connections,
+                    }],
+                    unchanged @ (GraphOp::AddInput { .. } | GraphOp::AddOutput { .. }) => {
+                        vec![unchanged]
+                    }
+                }
+            })
+            .collect();
+        ops.sort_by_key(op_phase);
+        Self { ops }
+    }
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    fn input_by_path(&self, path: &ParamPath) -> Option<InputId> {
+        let node_id = self.node_by_label(&path.node.0)?;
+        self[node_id].get_input(&path.name).ok()
+    }
+
+    fn output_by_path(&self, path: &ParamPath) -> Option<OutputId> {
+        let node_id = self.node_by_label(&path.node.0)?;
+        self[node_id].get_output(&path.name).ok()
+    }
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType>
+where
+    DataType: Clone + PartialEq,
+    ValueType: Clone + PartialEq,
+{
+    /// Computes the ordered list of operations that would turn `self` into `other`,
+    /// comparing nodes and params by label/name rather than by `SlotMap` key.
+    pub fn diff(&self, other: &Self) -> GraphPatch<DataType, ValueType> {
+        let mut ops = Vec::new();
+
+        for node_id in other.iter_nodes() {
+            if self.node_by_label(&other[node_id].label).is_none() {
+                ops.push(GraphOp::AddNode {
+                    label: other[node_id].label.clone(),
+                });
+            }
+        }
+        for node_id in self.iter_nodes() {
+            if other.node_by_label(&self[node_id].label).is_none() {
+                let inputs = self[node_id]
+                    .inputs
+                    .iter()
+                    .map(|(name, input_id)| {
+                        let input = self.get_input(*input_id);
+                        (
+                            name.clone(),
+                            input.typ.clone(),
+                            input.value().clone(),
+                            input.kind(),
+                            input.shown_inline,
+                        )
+                    })
+                    .collect();
+                let outputs = self[node_id]
+                    .outputs
+                    .iter()
+                    .map(|(name, output_id)| (name.clone(), self.get_output(*output_id).typ.clone()))
+                    .collect();
+                let connections = self[node_id]
+                    .inputs
+                    .iter()
+                    .filter_map(|(name, input_id)| {
+                        let output_id = self.connection(*input_id)?;
+                        let output_node = &self[self.outputs[output_id].node];
+                        let output_name = output_node
+                            .outputs
+                            .iter()
+                            .find(|(_, id)| *id == output_id)
+                            .unwrap()
+                            .0
+                            .clone();
+                        Some((
+                            name.clone(),
+                            ParamPath {
+                                node: NodePath(output_node.label.clone()),
+                                name: output_name,
+                            },
+                        ))
+                    })
+                    .collect();
+                ops.push(GraphOp::RemoveNode {
+                    label: self[node_id].label.clone(),
+                    inputs,
+                    outputs,
+                    connections,
+                });
+            }
+        }
+
+        for node_id in other.iter_nodes() {
+            let node_path = NodePath(other[node_id].label.clone());
+            let self_node_id = self.node_by_label(&node_path.0);
+
+            for (name, input_id) in &other[node_id].inputs {
+                let other_input = other.get_input(*input_id);
+                let self_input = self_node_id.and_then(|id| self[id].get_input(name).ok());
+                match self_input {
+                    None => ops.push(GraphOp::AddInput {
+                        node: node_path.clone(),
+                        name: name.clone(),
+                        typ: other_input.typ.clone(),
+                        value: other_input.value().clone(),
+                        kind: other_input.kind(),
+                        shown_inline: other_input.shown_inline,
+                    }),
+                    Some(self_input_id) => {
+                        let self_input = self.get_input(self_input_id);
+                        let input_path = ParamPath {
+                            node: node_path.clone(),
+                            name: name.clone(),
+                        };
+                        if self_input.typ != other_input.typ {
+                            let old_connection = self.connection(self_input_id).map(|output_id| {
+                                self.connection_paths(self_input_id, output_id).0
+                            });
+                            let new_connection = other.connection(*input_id).map(|output_id| {
+                                other.connection_paths(*input_id, output_id).0
+                            });
+                            ops.push(GraphOp::ChangeInputType {
+                                input: input_path,
+                                old_type: self_input.typ.clone(),
+                                old_value: self_input.value().clone(),
+                                new_type: other_input.typ.clone(),
+                                new_value: other_input.value().clone(),
+                                old_connection,
+                                new_connection,
+                            });
+                        } else if self_input.value() != other_input.value() {
+                            ops.push(GraphOp::SetInputValue {
+                                input: input_path,
+                                old_value: self_input.value().clone(),
+                                new_value: other_input.value().clone(),
+                            });
+                        }
+                    }
+                }
+            }
+
+            for (name, output_id) in &other[node_id].outputs {
+                let other_output = other.get_output(*output_id);
+                let self_output = self_node_id.and_then(|id| self[id].get_output(name).ok());
+                match self_output {
+                    None => ops.push(GraphOp::AddOutput {
+                        node: node_path.clone(),
+                        name: name.clone(),
+                        typ: other_output.typ.clone(),
+                    }),
+                    Some(self_output_id) => {
+                        let self_output = self.get_output(self_output_id);
+                        if self_output.typ != other_output.typ {
+                            let old_connections = self
+                                .iter_connections()
+                                .filter(|(_, out_id)| *out_id == self_output_id)
+                                .map(|(in_id, out_id)| self.connection_paths(in_id, out_id).1)
+                                .collect();
+                            let new_connections = other
+                                .iter_connections()
+                                .filter(|(_, out_id)| *out_id == *output_id)
+                                .map(|(in_id, out_id)| other.connection_paths(in_id, out_id).1)
+                                .collect();
+                            ops.push(GraphOp::ChangeOutputType {
+                                output: ParamPath {
+                                    node: node_path.clone(),
+                                    name: name.clone(),
+                                },
+                                old_type: self_output.typ.clone(),
+                                new_type: other_output.typ.clone(),
+                                old_connections,
+                                new_connections,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        // Disconnects are emitted (and later applied) before connects: applying a
+        // Disconnect drops whatever feeds the input, so when an input is rewired from
+        // one output to another it must run before the Connect that sets the new edge.
+        for (input_id, output_id) in self.iter_connections() {
+            let path = self.connection_paths(input_id, output_id);
+            let still_connected = other
+                .input_by_path(&path.1)
+                .and_then(|input| other.connection(input))
+                .map_or(false, |output| Some(output) == other.output_by_path(&path.0));
+            if !still_connected {
+                ops.push(GraphOp::Disconnect {
+                    output: path.0,
+                    input: path.1,
+                });
+            }
+        }
+        for (input_id, output_id) in other.iter_connections() {
+            let path = other.connection_paths(input_id, output_id);
+            let already_connected = self
+                .input_by_path(&path.1)
+                .and_then(|input| self.connection(input))
+                .map_or(false, |output| Some(output) == self.output_by_path(&path.0));
+            if !already_connected {
+                ops.push(GraphOp::Connect {
+                    output: path.0,
+                    input: path.1,
+                });
+            }
+        }
+
+        ops.sort_by_key(op_phase);
+        GraphPatch { ops }
+    }
+
+    fn connection_paths(&self, input_id: InputId, output_id: OutputId) -> (ParamPath, ParamPath) {
+        let input_node = &self[self.get_input(input_id).node()];
+        let output_node = &self[self.outputs[output_id].node];
+        let input_name = input_node
+            .inputs
+            .iter()
+            .find(|(_, id)| *id == input_id)
+            .unwrap()
+            .0
+            .clone();
+        let output_name = output_node
+            .outputs
+            .iter()
+            .find(|(_, id)| *id == output_id)
+            .unwrap()
+            .0
+            .clone();
+        (
+            ParamPath {
+                node: NodePath(output_node.label.clone()),
+                name: output_name,
+            },
+            ParamPath {
+                node: NodePath(input_node.label.clone()),
+                name: input_name,
+            },
+        )
+    }
+
+    /// Applies `patch` to `self`, resolving each op's node/param by label rather than
+    /// by `SlotMap` key.
+    pub fn apply(&mut self, patch: &GraphPatch<DataType, ValueType>)
+    where
+        NodeData: Default,
+    {
+        for op in patch.ops.iter().cloned() {
+            match op {
+                GraphOp::AddNode { label } => {
+                    self.add_node(label, NodeData::default(), |_, _| {});
+                }
+                GraphOp::RemoveNode { label, .. } => {
+                    if let Some(node_id) = self.node_by_label(&label) {
+                        self.remove_node(node_id);
+                    }
+                }
+                GraphOp::AddInput {
+                    node,
+                    name,
+                    typ,
+                    value,
+                    kind,
+                    shown_inline,
+                } => {
+                    if let Some(node_id) = self.node_by_label(&node.0) {
+                        self.add_input_param(node_id, name, typ, value, kind, shown_inline);
+                    }
+                }
+                GraphOp::AddOutput { node, name, typ } => {
+                    if let Some(node_id) = self.node_by_label(&node.0) {
+                        self.add_output_param(node_id, name, typ);
+                    }
+                }
+                GraphOp::Connect { output, input } => {
+                    if let (Some(output_id), Some(input_id)) =
+                        (self.output_by_path(&output), self.input_by_path(&input))
+                    {
+                        self.add_connection(output_id, input_id);
+                    }
+                }
+                GraphOp::Disconnect { input, .. } => {
+                    if let Some(input_id) = self.input_by_path(&input) {
+                        self.remove_connection(input_id);
+                    }
+                }
+                GraphOp::ChangeInputType {
+                    input,
+                    new_type,
+                    new_value,
+                    new_connection,
+                    ..
+                } => {
+                    if let Some(node_id) = self.node_by_label(&input.node.0) {
+                        let _ =
+                            self.change_node_input_type(node_id, &input.name, new_type, new_value);
+                    }
+                    // change_node_input_type unconditionally drops the input's
+                    // connection, so restore whatever should still be feeding it.
+                    if let Some(output_path) = new_connection {
+                        if let (Some(output_id), Some(input_id)) =
+                            (self.output_by_path(&output_path), self.input_by_path(&input))
+                        {
+                            self.add_connection(output_id, input_id);
+                        }
+                    }
+                }
+                GraphOp::SetInputValue { input, new_value, .. } => {
+                    if let Some(input_id) = self.input_by_path(&input) {
+                        self.set_input_value(input_id, new_value);
+                    }
+                }
+                GraphOp::ChangeOutputType {
+                    output,
+                    new_type,
+                    new_connections,
+                    ..
+                } => {
+                    if let Some(node_id) = self.node_by_label(&output.node.0) {
+                        let _ = self.change_node_output_type(node_id, &output.name, new_type);
+                    }
+                    // change_node_output_type drops every connection fed by this
+                    // output, so restore whichever ones should still exist.
+                    if let Some(output_id) = self.output_by_path(&output) {
+                        for input_path in new_connections {
+                            if let Some(input_id) = self.input_by_path(&input_path) {
+                                self.add_connection(output_id, input_id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum TestType {
+        Number,
+        Text,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum TestValue {
+        Number(f64),
+        Text(String),
+    }
+
+    fn number_source(graph: &mut Graph<(), TestType, TestValue>, label: &str) -> (NodeId, OutputId) {
+        let node_id = graph.add_node(label.to_string(), (), |graph, node_id| {
+            graph.add_output_param(node_id, "out".into(), TestType::Number);
+        });
+        let output_id = graph[node_id].get_output("out").unwrap();
+        (node_id, output_id)
+    }
+
+    fn sink_with_input(
+        graph: &mut Graph<(), TestType, TestValue>,
+        label: &str,
+        value: TestValue,
+    ) -> (NodeId, InputId) {
+        let node_id = graph.add_node(label.to_string(), (), |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "in".into(),
+                TestType::Number,
+                value,
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+        });
+        let input_id = graph[node_id].get_input("in").unwrap();
+        (node_id, input_id)
+    }
+
+    #[test]
+    fn diff_apply_round_trip_preserves_connection_on_value_only_edit() {
+        let mut before = Graph::<(), TestType, TestValue>::new();
+        let (_, source_output) = number_source(&mut before, "source");
+        let (_, sink_input) = sink_with_input(&mut before, "sink", TestValue::Number(1.0));
+        before.add_connection(source_output, sink_input);
+
+        let mut after = Graph::<(), TestType, TestValue>::new();
+        let (_, source_output) = number_source(&mut after, "source");
+        let (_, sink_input) = sink_with_input(&mut after, "sink", TestValue::Number(2.0));
+        after.add_connection(source_output, sink_input);
+
+        let patch = before.diff(&after);
+        assert!(
+            patch
+                .ops
+                .iter()
+                .all(|op| !matches!(op, GraphOp::ChangeInputType { .. })),
+            "a value-only edit should diff to SetInputValue, not ChangeInputType"
+        );
+
+        before.apply(&patch);
+        let sink_id = before.node_by_label("sink").unwrap();
+        let sink_input_id = before[sink_id].get_input("in").unwrap();
+        assert_eq!(before.get_input(sink_input_id).value(), &TestValue::Number(2.0));
+        assert!(
+            before.connection(sink_input_id).is_some(),
+            "the connection must survive a value-only edit"
+        );
+    }
+
+    #[test]
+    fn diff_apply_round_trip_preserves_connection_on_type_change() {
+        let mut before = Graph::<(), TestType, TestValue>::new();
+        let (_, source_output) = number_source(&mut before, "source");
+        let (_, sink_input) = sink_with_input(&mut before, "sink", TestValue::Number(1.0));
+        before.add_connection(source_output, sink_input);
+
+        let mut after = Graph::<(), TestType, TestValue>::new();
+        let (_, source_output) = number_source(&mut after, "source");
+        let after_sink_id = after.add_node("sink".to_string(), (), |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "in".into(),
+                TestType::Text,
+                TestValue::Text("hi".into()),
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+        });
+        let after_sink_input = after[after_sink_id].get_input("in").unwrap();
+        after.add_connection(source_output, after_sink_input);
+
+        let patch = before.diff(&after);
+        before.apply(&patch);
+
+        let sink_id = before.node_by_label("sink").unwrap();
+        let sink_input_id = before[sink_id].get_input("in").unwrap();
+        assert_eq!(before.get_input(sink_input_id).typ, TestType::Text);
+        assert!(
+            before.connection(sink_input_id).is_some(),
+            "a type change to an input that stays connected must not sever the connection"
+        );
+    }
+
+    fn build_connected_pair(value: TestValue) -> Graph<(), TestType, TestValue> {
+        let mut graph = Graph::<(), TestType, TestValue>::new();
+        let (_, source_output) = number_source(&mut graph, "source");
+        let (_, sink_input) = sink_with_input(&mut graph, "sink", value);
+        graph.add_connection(source_output, sink_input);
+        graph
+    }
+
+    #[test]
+    fn invert_undoes_a_value_only_edit() {
+        let before = build_connected_pair(TestValue::Number(1.0));
+        let after = build_connected_pair(TestValue::Number(2.0));
+
+        let patch = before.diff(&after);
+        let mut round_tripped = build_connected_pair(TestValue::Number(1.0));
+        round_tripped.apply(&patch);
+        round_tripped.apply(&patch.invert());
+
+        let sink_input_id = round_tripped[round_tripped.node_by_label("sink").unwrap()]
+            .get_input("in")
+            .unwrap();
+        assert_eq!(
+            round_tripped.get_input(sink_input_id).value(),
+            &TestValue::Number(1.0)
+        );
+        assert!(round_tripped.connection(sink_input_id).is_some());
+    }
+}